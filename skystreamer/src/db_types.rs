@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::PostData;
+
+/// A post as it's handed to an [`crate::exporter::Exporter`], shaped for
+/// storage rather than for the wire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Post {
+    pub did: String,
+    pub rkey: String,
+    pub cid: String,
+    pub text: String,
+    pub langs: Vec<String>,
+    pub created_at: String,
+    /// At-URI/CID of the thread root, `None` for a top-level post.
+    pub reply_root_uri: Option<String>,
+    pub reply_root_cid: Option<String>,
+    /// At-URI/CID of the immediate parent, `None` for a top-level post.
+    pub reply_parent_uri: Option<String>,
+    pub reply_parent_cid: Option<String>,
+    /// Short discriminant of the embed union, e.g. `"images"`/`"external"`,
+    /// `None` if the post has no embed.
+    pub embed_type: Option<String>,
+}
+
+impl Post {
+    pub fn new(post: PostData, rkey: String) -> Self {
+        let reply = post.record.reply;
+        let embed_type = post.record.embed.as_ref().map(embed_type_name);
+
+        Post {
+            did: post.did,
+            rkey,
+            cid: post.cid,
+            text: post.record.text,
+            langs: post
+                .record
+                .langs
+                .unwrap_or_default()
+                .into_iter()
+                .map(|l| l.as_ref().to_string())
+                .collect(),
+            created_at: post.record.created_at.as_str().to_string(),
+            reply_root_uri: reply.as_ref().map(|r| r.root.uri.clone()),
+            reply_root_cid: reply.as_ref().map(|r| r.root.cid.to_string()),
+            reply_parent_uri: reply.as_ref().map(|r| r.parent.uri.clone()),
+            reply_parent_cid: reply.as_ref().map(|r| r.parent.cid.to_string()),
+            embed_type,
+        }
+    }
+}
+
+/// Names which variant of the post embed union is present, so exporters
+/// that flatten to columns (e.g. Parquet) can carry it as a single string
+/// rather than a nested, per-embed-kind shape.
+fn embed_type_name(
+    embed: &atrium_api::types::Union<atrium_api::app::bsky::feed::post::RecordEmbedRefs>,
+) -> String {
+    use atrium_api::app::bsky::feed::post::RecordEmbedRefs;
+    use atrium_api::types::Union;
+
+    match embed {
+        Union::Refs(RecordEmbedRefs::AppBskyEmbedImagesMain(_)) => "images".to_string(),
+        Union::Refs(RecordEmbedRefs::AppBskyEmbedVideoMain(_)) => "video".to_string(),
+        Union::Refs(RecordEmbedRefs::AppBskyEmbedExternalMain(_)) => "external".to_string(),
+        Union::Refs(RecordEmbedRefs::AppBskyEmbedRecordMain(_)) => "record".to_string(),
+        Union::Refs(RecordEmbedRefs::AppBskyEmbedRecordWithMediaMain(_)) => {
+            "record_with_media".to_string()
+        }
+        Union::Unknown(data) => data.r#type.clone(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Like {
+    pub did: String,
+    pub rkey: String,
+    pub cid: String,
+    pub subject: String,
+    pub created_at: String,
+}
+
+impl Like {
+    pub fn new(
+        did: String,
+        rkey: String,
+        cid: String,
+        record: atrium_api::app::bsky::feed::like::Record,
+    ) -> Self {
+        Like {
+            did,
+            rkey,
+            cid,
+            subject: record.subject.uri.clone(),
+            created_at: record.created_at.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Follow {
+    pub did: String,
+    pub rkey: String,
+    pub cid: String,
+    pub subject: String,
+    pub created_at: String,
+}
+
+impl Follow {
+    pub fn new(
+        did: String,
+        rkey: String,
+        cid: String,
+        record: atrium_api::app::bsky::graph::follow::Record,
+    ) -> Self {
+        Follow {
+            did,
+            rkey,
+            cid,
+            subject: record.subject.to_string(),
+            created_at: record.created_at.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Repost {
+    pub did: String,
+    pub rkey: String,
+    pub cid: String,
+    pub subject: String,
+    pub created_at: String,
+}
+
+impl Repost {
+    pub fn new(
+        did: String,
+        rkey: String,
+        cid: String,
+        record: atrium_api::app::bsky::feed::repost::Record,
+    ) -> Self {
+        Repost {
+            did,
+            rkey,
+            cid,
+            subject: record.subject.uri.clone(),
+            created_at: record.created_at.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub did: String,
+    pub rkey: String,
+    pub cid: String,
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+}
+
+impl Profile {
+    pub fn new(
+        did: String,
+        rkey: String,
+        cid: String,
+        record: atrium_api::app::bsky::actor::profile::Record,
+    ) -> Self {
+        Profile {
+            did,
+            rkey,
+            cid,
+            display_name: record.display_name.clone(),
+            description: record.description.clone(),
+        }
+    }
+}
+
+/// A tombstone for a deleted record: the `delete` op carries a path (and
+/// therefore a collection + rkey) but no block, so this is all downstream
+/// stores get to stay consistent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub did: String,
+    pub collection: String,
+    pub rkey: String,
+}
+
+/// Every record type the firehose dispatcher can route to an
+/// [`crate::exporter::Exporter`]. This is what turns the tool from a
+/// post-only scraper into a full AT Protocol repo mirror: every collection
+/// we understand gets a typed variant, and deletes get a tombstone instead
+/// of being silently dropped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Record {
+    Post(Post),
+    Like(Like),
+    Follow(Follow),
+    Repost(Repost),
+    Profile(Profile),
+    Deleted(Tombstone),
+}
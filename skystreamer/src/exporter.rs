@@ -0,0 +1,852 @@
+use async_compression::tokio::write::{GzipEncoder, ZstdEncoder};
+use atrium_api::types::Collection;
+use color_eyre::{eyre::eyre, Result};
+use object_store::{buffered::BufWriter, path::Path as ObjectPath, ObjectStore};
+use surrealdb::{engine::any::Any, Surreal};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::db_types::{Follow, Like, Post, Profile, Record, Repost, Tombstone};
+
+/// Compression codec applied to a file exporter's output sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Default)]
+pub enum FileCompression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl FileCompression {
+    /// Infers a codec from `path`'s extension, so `out.jsonl.zst` "just
+    /// works" without also passing `--compression zstd`.
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".zst") {
+            FileCompression::Zstd
+        } else if path.ends_with(".gz") {
+            FileCompression::Gzip
+        } else {
+            FileCompression::None
+        }
+    }
+}
+
+/// Wraps `writer` in a compressing encoder for `codec`, or returns it
+/// unwrapped for `FileCompression::None`.
+fn compressing_writer<W: AsyncWrite + Send + Unpin + 'static>(
+    writer: W,
+    codec: FileCompression,
+) -> Box<dyn AsyncWrite + Send + Unpin> {
+    match codec {
+        FileCompression::None => Box::new(writer),
+        FileCompression::Gzip => Box::new(GzipEncoder::new(writer)),
+        FileCompression::Zstd => Box::new(ZstdEncoder::new(writer)),
+    }
+}
+
+/// A backend that persists decoded records off the firehose: posts, likes,
+/// follows, reposts, profile updates, and tombstones for deletes.
+#[async_trait::async_trait]
+pub trait Exporter: Send + Sync {
+    async fn export(&mut self, record: Record) -> Result<()>;
+
+    /// Flushes and finalizes any buffered state on graceful shutdown, e.g.
+    /// a partial Parquet row group or a compressing file sink's trailing
+    /// frames. Default no-op for exporters with nothing to buffer.
+    async fn shutdown(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A backend that stores each record shape individually, keyed on
+/// `(did, rkey)`, rather than on the generic [`Record`] enum. Any store
+/// implementing this gets an [`Exporter`] for free via the blanket impl
+/// below, so adding a new backend only means teaching it how to persist
+/// and retract each concrete record type.
+#[async_trait::async_trait]
+pub trait RecordStore: Send + Sync {
+    async fn put_post(&mut self, post: Post) -> Result<()>;
+    async fn put_like(&mut self, like: Like) -> Result<()>;
+    async fn put_follow(&mut self, follow: Follow) -> Result<()>;
+    async fn put_repost(&mut self, repost: Repost) -> Result<()>;
+    async fn put_profile(&mut self, profile: Profile) -> Result<()>;
+    async fn delete(&mut self, tombstone: Tombstone) -> Result<()>;
+
+    /// Flushes any buffered writes on graceful shutdown. Default no-op for
+    /// stores that write through immediately.
+    async fn shutdown(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: RecordStore> Exporter for S {
+    async fn export(&mut self, record: Record) -> Result<()> {
+        match record {
+            Record::Post(post) => self.put_post(post).await,
+            Record::Like(like) => self.put_like(like).await,
+            Record::Follow(follow) => self.put_follow(follow).await,
+            Record::Repost(repost) => self.put_repost(repost).await,
+            Record::Profile(profile) => self.put_profile(profile).await,
+            Record::Deleted(tombstone) => self.delete(tombstone).await,
+        }
+    }
+
+    async fn shutdown(&mut self) -> Result<()> {
+        RecordStore::shutdown(self).await
+    }
+}
+
+pub struct JsonlExporter {
+    sink: Box<dyn AsyncWrite + Send + Unpin>,
+}
+
+impl JsonlExporter {
+    pub fn new(file: tokio::fs::File, compression: FileCompression) -> Self {
+        JsonlExporter {
+            sink: compressing_writer(file, compression),
+        }
+    }
+
+}
+
+#[async_trait::async_trait]
+impl Exporter for JsonlExporter {
+    async fn export(&mut self, record: Record) -> Result<()> {
+        let line = serde_json::to_string(&record)?;
+        self.sink.write_all(line.as_bytes()).await?;
+        self.sink.write_all(b"\n").await?;
+        Ok(())
+    }
+
+    /// Finalizes the underlying encoder, flushing any trailing frames so a
+    /// compressed file isn't left truncated.
+    async fn shutdown(&mut self) -> Result<()> {
+        self.sink.shutdown().await?;
+        Ok(())
+    }
+}
+
+pub struct CsvExporter {
+    sink: Box<dyn AsyncWrite + Send + Unpin>,
+}
+
+impl CsvExporter {
+    pub fn new(file: tokio::fs::File, compression: FileCompression) -> Self {
+        CsvExporter {
+            sink: compressing_writer(file, compression),
+        }
+    }
+
+}
+
+#[async_trait::async_trait]
+impl Exporter for CsvExporter {
+    async fn export(&mut self, record: Record) -> Result<()> {
+        // CSV is row-oriented and only the `Post` shape has a stable set of
+        // columns today; other record types pass through the JSONL/object
+        // store/SurrealDB exporters instead.
+        let Record::Post(post) = record else {
+            tracing::trace!("CSV exporter only handles posts, skipping record");
+            return Ok(());
+        };
+        let line = format!(
+            "{},{},{},{},{}\n",
+            post.did,
+            post.cid,
+            post.created_at,
+            post.langs.join("|"),
+            post.text.replace('\n', " ").replace(',', ";")
+        );
+        self.sink.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Finalizes the underlying encoder, flushing any trailing frames so a
+    /// compressed file isn't left truncated.
+    async fn shutdown(&mut self) -> Result<()> {
+        self.sink.shutdown().await?;
+        Ok(())
+    }
+}
+
+pub struct SurrealDbExporter {
+    db: Surreal<Any>,
+}
+
+impl SurrealDbExporter {
+    pub fn new(db: Surreal<Any>) -> Self {
+        SurrealDbExporter { db }
+    }
+}
+
+#[async_trait::async_trait]
+impl RecordStore for SurrealDbExporter {
+    async fn put_post(&mut self, post: Post) -> Result<()> {
+        let id = format!("{}:{}", post.did, post.rkey);
+        let _: Option<Post> = self.db.upsert(("post", id)).content(post).await?;
+        Ok(())
+    }
+
+    async fn put_like(&mut self, like: Like) -> Result<()> {
+        let id = format!("{}:{}", like.did, like.rkey);
+        let _: Option<Like> = self.db.upsert(("like", id)).content(like).await?;
+        Ok(())
+    }
+
+    async fn put_follow(&mut self, follow: Follow) -> Result<()> {
+        let id = format!("{}:{}", follow.did, follow.rkey);
+        let _: Option<Follow> = self.db.upsert(("follow", id)).content(follow).await?;
+        Ok(())
+    }
+
+    async fn put_repost(&mut self, repost: Repost) -> Result<()> {
+        let id = format!("{}:{}", repost.did, repost.rkey);
+        let _: Option<Repost> = self.db.upsert(("repost", id)).content(repost).await?;
+        Ok(())
+    }
+
+    async fn put_profile(&mut self, profile: Profile) -> Result<()> {
+        let id = format!("{}:{}", profile.did, profile.rkey);
+        let _: Option<Profile> = self.db.upsert(("profile", id)).content(profile).await?;
+        Ok(())
+    }
+
+    async fn delete(&mut self, tombstone: Tombstone) -> Result<()> {
+        // Map the tombstone's NSID onto the table the `put_*` methods write to.
+        let table = match tombstone.collection.as_str() {
+            c if c == atrium_api::app::bsky::feed::Post::NSID => "post",
+            c if c == atrium_api::app::bsky::feed::Like::NSID => "like",
+            c if c == atrium_api::app::bsky::graph::Follow::NSID => "follow",
+            c if c == atrium_api::app::bsky::feed::Repost::NSID => "repost",
+            c if c == atrium_api::app::bsky::actor::Profile::NSID => "profile",
+            other => {
+                tracing::trace!(collection = other, "no SurrealDB table for collection, skipping delete");
+                return Ok(());
+            }
+        };
+        self.db
+            .query("DELETE type::table($collection) WHERE did = $did AND rkey = $rkey")
+            .bind(("collection", table))
+            .bind(("did", tombstone.did))
+            .bind(("rkey", tombstone.rkey))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Compression codec for row-group pages in a Parquet file.
+#[derive(Debug, Clone, Copy, clap::ValueEnum, Default)]
+pub enum ParquetCompression {
+    #[default]
+    Snappy,
+    Zstd,
+    None,
+}
+
+impl From<ParquetCompression> for parquet::basic::Compression {
+    fn from(codec: ParquetCompression) -> Self {
+        match codec {
+            ParquetCompression::Snappy => parquet::basic::Compression::SNAPPY,
+            ParquetCompression::Zstd => {
+                parquet::basic::Compression::ZSTD(parquet::basic::ZstdLevel::default())
+            }
+            ParquetCompression::None => parquet::basic::Compression::UNCOMPRESSED,
+        }
+    }
+}
+
+/// Writes firehose posts as columnar Parquet, buffering rows into a
+/// [`RecordBatch`](arrow::record_batch::RecordBatch) and flushing a row
+/// group once `batch_size` rows have accumulated so the file stays
+/// queryable (e.g. with DuckDB) without holding the whole stream in memory.
+pub struct ParquetExporter {
+    /// `None` once [`ParquetExporter::shutdown`] has taken and closed it,
+    /// since closing writes the file footer and consumes the writer.
+    writer: Option<parquet::arrow::AsyncArrowWriter<tokio::fs::File>>,
+    schema: std::sync::Arc<arrow::datatypes::Schema>,
+    batch_size: usize,
+    flush_interval: std::time::Duration,
+    last_flush: std::time::Instant,
+    dids: Vec<String>,
+    cids: Vec<String>,
+    texts: Vec<String>,
+    langs: Vec<Vec<String>>,
+    created_ats: Vec<i64>,
+    reply_root_uris: Vec<Option<String>>,
+    reply_root_cids: Vec<Option<String>>,
+    reply_parent_uris: Vec<Option<String>>,
+    reply_parent_cids: Vec<Option<String>>,
+    embed_types: Vec<Option<String>>,
+}
+
+impl ParquetExporter {
+    /// `batch_size` rows are buffered before a row group is flushed;
+    /// `row_group_size` and `compression` are forwarded to the underlying
+    /// `ArrowWriter` properties. A partial batch is also flushed when a
+    /// record arrives after `flush_interval` has elapsed since the last
+    /// flush; the interval is only checked on `export`, so while no records
+    /// arrive, buffered rows stay in memory until the next record or
+    /// shutdown.
+    pub fn new(
+        file: tokio::fs::File,
+        batch_size: usize,
+        row_group_size: usize,
+        compression: ParquetCompression,
+        flush_interval: std::time::Duration,
+    ) -> Result<Self> {
+        let schema = std::sync::Arc::new(arrow::datatypes::Schema::new(vec![
+            arrow::datatypes::Field::new("did", arrow::datatypes::DataType::Utf8, false),
+            arrow::datatypes::Field::new("cid", arrow::datatypes::DataType::Utf8, false),
+            arrow::datatypes::Field::new("text", arrow::datatypes::DataType::Utf8, false),
+            arrow::datatypes::Field::new(
+                "langs",
+                arrow::datatypes::DataType::List(std::sync::Arc::new(
+                    arrow::datatypes::Field::new("item", arrow::datatypes::DataType::Utf8, true),
+                )),
+                true,
+            ),
+            arrow::datatypes::Field::new(
+                "created_at",
+                arrow::datatypes::DataType::Timestamp(
+                    arrow::datatypes::TimeUnit::Millisecond,
+                    None,
+                ),
+                false,
+            ),
+            arrow::datatypes::Field::new("reply_root_uri", arrow::datatypes::DataType::Utf8, true),
+            arrow::datatypes::Field::new("reply_root_cid", arrow::datatypes::DataType::Utf8, true),
+            arrow::datatypes::Field::new(
+                "reply_parent_uri",
+                arrow::datatypes::DataType::Utf8,
+                true,
+            ),
+            arrow::datatypes::Field::new(
+                "reply_parent_cid",
+                arrow::datatypes::DataType::Utf8,
+                true,
+            ),
+            arrow::datatypes::Field::new("embed_type", arrow::datatypes::DataType::Utf8, true),
+        ]));
+
+        let props = parquet::file::properties::WriterProperties::builder()
+            .set_max_row_group_size(row_group_size)
+            .set_compression(compression.into())
+            .build();
+
+        let writer = parquet::arrow::AsyncArrowWriter::try_new(file, schema.clone(), Some(props))?;
+
+        Ok(ParquetExporter {
+            writer: Some(writer),
+            schema,
+            batch_size,
+            flush_interval,
+            last_flush: std::time::Instant::now(),
+            dids: Vec::new(),
+            cids: Vec::new(),
+            texts: Vec::new(),
+            langs: Vec::new(),
+            created_ats: Vec::new(),
+            reply_root_uris: Vec::new(),
+            reply_root_cids: Vec::new(),
+            reply_parent_uris: Vec::new(),
+            reply_parent_cids: Vec::new(),
+            embed_types: Vec::new(),
+        })
+    }
+
+    fn to_record_batch(&mut self) -> Result<arrow::record_batch::RecordBatch> {
+        let langs_builder = {
+            let mut builder = arrow::array::ListBuilder::new(arrow::array::StringBuilder::new());
+            for row in self.langs.drain(..) {
+                for lang in row {
+                    builder.values().append_value(lang);
+                }
+                builder.append(true);
+            }
+            builder.finish()
+        };
+
+        let batch = arrow::record_batch::RecordBatch::try_new(
+            self.schema.clone(),
+            vec![
+                std::sync::Arc::new(arrow::array::StringArray::from(std::mem::take(
+                    &mut self.dids,
+                ))),
+                std::sync::Arc::new(arrow::array::StringArray::from(std::mem::take(
+                    &mut self.cids,
+                ))),
+                std::sync::Arc::new(arrow::array::StringArray::from(std::mem::take(
+                    &mut self.texts,
+                ))),
+                std::sync::Arc::new(langs_builder),
+                std::sync::Arc::new(arrow::array::TimestampMillisecondArray::from(
+                    std::mem::take(&mut self.created_ats),
+                )),
+                std::sync::Arc::new(arrow::array::StringArray::from(std::mem::take(
+                    &mut self.reply_root_uris,
+                ))),
+                std::sync::Arc::new(arrow::array::StringArray::from(std::mem::take(
+                    &mut self.reply_root_cids,
+                ))),
+                std::sync::Arc::new(arrow::array::StringArray::from(std::mem::take(
+                    &mut self.reply_parent_uris,
+                ))),
+                std::sync::Arc::new(arrow::array::StringArray::from(std::mem::take(
+                    &mut self.reply_parent_cids,
+                ))),
+                std::sync::Arc::new(arrow::array::StringArray::from(std::mem::take(
+                    &mut self.embed_types,
+                ))),
+            ],
+        )?;
+        Ok(batch)
+    }
+
+    /// Flushes whatever rows are currently buffered as a (possibly short)
+    /// row group. Called from `export` on the batch-size boundary or once
+    /// `flush_interval` has elapsed, and on shutdown so buffered rows are
+    /// never silently dropped.
+    pub async fn flush(&mut self) -> Result<()> {
+        if self.dids.is_empty() {
+            return Ok(());
+        }
+        let batch = self.to_record_batch()?;
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| eyre!("Parquet writer used after shutdown"))?;
+        writer.write(&batch).await?;
+        writer.flush().await?;
+        self.last_flush = std::time::Instant::now();
+        Ok(())
+    }
+
+    /// Flushes any buffered rows and writes the Parquet footer, making the
+    /// file readable. Idempotent: a second call is a no-op.
+    pub async fn close(&mut self) -> Result<()> {
+        self.flush().await?;
+        if let Some(writer) = self.writer.take() {
+            writer.close().await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Exporter for ParquetExporter {
+    async fn export(&mut self, record: Record) -> Result<()> {
+        // The Arrow schema is shaped for posts only; other record types
+        // skip this exporter until it grows columns for them.
+        let Record::Post(post) = record else {
+            tracing::trace!("Parquet exporter only handles posts, skipping record");
+            return Ok(());
+        };
+        self.dids.push(post.did);
+        self.cids.push(post.cid);
+        self.texts.push(post.text);
+        self.langs.push(post.langs);
+        let created_at = chrono::DateTime::parse_from_rfc3339(&post.created_at)
+            .map(|dt| dt.timestamp_millis())
+            .unwrap_or_default();
+        self.created_ats.push(created_at);
+        self.reply_root_uris.push(post.reply_root_uri);
+        self.reply_root_cids.push(post.reply_root_cid);
+        self.reply_parent_uris.push(post.reply_parent_uri);
+        self.reply_parent_cids.push(post.reply_parent_cid);
+        self.embed_types.push(post.embed_type);
+
+        if self.dids.len() >= self.batch_size || self.last_flush.elapsed() >= self.flush_interval {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    async fn shutdown(&mut self) -> Result<()> {
+        self.close().await
+    }
+}
+
+/// Streams JSONL directly to an S3-compatible bucket, rolling to a new
+/// object key on a size or time boundary so large partitions never have to
+/// be buffered to local disk. Writes go through `object_store`'s buffered
+/// multipart upload, so a partition can grow well past a single PUT's size
+/// limit.
+pub struct ObjectStoreExporter {
+    store: std::sync::Arc<dyn ObjectStore>,
+    prefix: String,
+    compression: FileCompression,
+    roll_size_bytes: u64,
+    roll_interval: std::time::Duration,
+    current: Box<dyn AsyncWrite + Send + Unpin>,
+    current_key: ObjectPath,
+    bytes_written: u64,
+    opened_at: std::time::Instant,
+    part: u64,
+}
+
+impl ObjectStoreExporter {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        bucket: &str,
+        region: Option<String>,
+        endpoint: Option<String>,
+        access_key_id: Option<String>,
+        secret_access_key: Option<String>,
+        prefix: String,
+        compression: FileCompression,
+        roll_size_bytes: u64,
+        roll_interval: std::time::Duration,
+    ) -> Result<Self> {
+        let mut builder = object_store::aws::AmazonS3Builder::new().with_bucket_name(bucket);
+        if let Some(region) = region {
+            builder = builder.with_region(region);
+        }
+        if let Some(endpoint) = endpoint {
+            builder = builder.with_endpoint(endpoint);
+        }
+        if let (Some(key), Some(secret)) = (access_key_id, secret_access_key) {
+            builder = builder.with_access_key_id(key).with_secret_access_key(secret);
+        }
+        // With no static credentials, `object_store` falls back to the
+        // default AWS chain (web identity, then instance metadata), so this
+        // works unmodified both locally and in-cluster.
+        let store: std::sync::Arc<dyn ObjectStore> = std::sync::Arc::new(builder.build()?);
+
+        let current_key = Self::next_key(&prefix, 0, compression);
+        let current = compressing_writer(
+            BufWriter::new(store.clone(), current_key.clone()),
+            compression,
+        );
+
+        Ok(ObjectStoreExporter {
+            store,
+            prefix,
+            compression,
+            roll_size_bytes,
+            roll_interval,
+            current,
+            current_key,
+            bytes_written: 0,
+            opened_at: std::time::Instant::now(),
+            part: 0,
+        })
+    }
+
+    fn next_key(prefix: &str, part: u64, compression: FileCompression) -> ObjectPath {
+        let now = chrono::Utc::now();
+        let ext = match compression {
+            FileCompression::None => "jsonl",
+            FileCompression::Gzip => "jsonl.gz",
+            FileCompression::Zstd => "jsonl.zst",
+        };
+        ObjectPath::from(format!(
+            "{prefix}/{y:04}/{m:02}/{d:02}/{h:02}/part-{part}.{ext}",
+            y = now.format("%Y"),
+            m = now.format("%m"),
+            d = now.format("%d"),
+            h = now.format("%H"),
+        ))
+    }
+
+    fn should_roll(&self) -> bool {
+        self.bytes_written >= self.roll_size_bytes || self.opened_at.elapsed() >= self.roll_interval
+    }
+
+    async fn roll(&mut self) -> Result<()> {
+        self.part += 1;
+        let next_key = Self::next_key(&self.prefix, self.part, self.compression);
+        let mut next = compressing_writer(
+            BufWriter::new(self.store.clone(), next_key.clone()),
+            self.compression,
+        );
+        std::mem::swap(&mut self.current, &mut next);
+        next.shutdown().await?;
+
+        self.current_key = next_key;
+        self.bytes_written = 0;
+        self.opened_at = std::time::Instant::now();
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Exporter for ObjectStoreExporter {
+    async fn export(&mut self, record: Record) -> Result<()> {
+        if self.should_roll() {
+            self.roll().await?;
+        }
+
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        self.bytes_written += line.len() as u64;
+        self.current.write_all(&line).await?;
+        Ok(())
+    }
+
+    /// Finalizes the current partition so its last, still-open multipart
+    /// upload actually lands in the bucket instead of being abandoned.
+    async fn shutdown(&mut self) -> Result<()> {
+        self.current.shutdown().await?;
+        Ok(())
+    }
+}
+
+/// One buffered write, queued until the next flush commits it inside a
+/// single transaction.
+enum PendingWrite {
+    Post(Post),
+    Like(Like),
+    Follow(Follow),
+    Repost(Repost),
+    Profile(Profile),
+    Delete(Tombstone),
+}
+
+/// Mirrors [`SurrealDbExporter`] against a relational store instead, for
+/// users who already run Postgres and don't want to stand up SurrealDB just
+/// for the firehose. Writes are buffered and applied inside a single
+/// transaction once `batch_size` records have queued up, or when a record
+/// is queued after `flush_interval` has elapsed, so a resumed cursor can safely replay a
+/// few records without double-counting: every insert is an upsert keyed on
+/// `(did, rkey)`.
+pub struct PostgresExporter {
+    pool: sqlx::PgPool,
+    batch_size: usize,
+    flush_interval: std::time::Duration,
+    last_flush: std::time::Instant,
+    pending: Vec<PendingWrite>,
+}
+
+impl PostgresExporter {
+    /// Connects to `database_url` and runs the migrations bundled under
+    /// `migrations/` so a fresh database ends up with the same shape as
+    /// `schema.surql` gives SurrealDB.
+    pub async fn new(
+        database_url: &str,
+        batch_size: usize,
+        flush_interval: std::time::Duration,
+    ) -> Result<Self> {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await?;
+
+        sqlx::migrate!("./migrations").run(&pool).await?;
+
+        Ok(PostgresExporter {
+            pool,
+            batch_size,
+            flush_interval,
+            last_flush: std::time::Instant::now(),
+            pending: Vec::new(),
+        })
+    }
+
+    async fn enqueue(&mut self, write: PendingWrite) -> Result<()> {
+        self.pending.push(write);
+        if self.pending.len() >= self.batch_size || self.last_flush.elapsed() >= self.flush_interval
+        {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Applies every buffered write inside one transaction. Called from
+    /// `enqueue` on the batch-size boundary or once `flush_interval` has
+    /// elapsed, and on shutdown; the interval is only checked when a write
+    /// is queued, so while no records arrive the buffered rows are held
+    /// until the next one or shutdown.
+    pub async fn flush(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            self.last_flush = std::time::Instant::now();
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        for write in self.pending.drain(..) {
+            match write {
+                PendingWrite::Post(post) => {
+                    sqlx::query!(
+                        "INSERT INTO post (did, rkey, cid, text, langs, created_at)
+                         VALUES ($1, $2, $3, $4, $5, $6)
+                         ON CONFLICT (did, rkey) DO UPDATE
+                         SET cid = EXCLUDED.cid,
+                             text = EXCLUDED.text,
+                             langs = EXCLUDED.langs,
+                             created_at = EXCLUDED.created_at",
+                        post.did,
+                        post.rkey,
+                        post.cid,
+                        post.text,
+                        &post.langs,
+                        post.created_at,
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                PendingWrite::Like(like) => {
+                    sqlx::query!(
+                        "INSERT INTO like_ (did, rkey, cid, subject, created_at)
+                         VALUES ($1, $2, $3, $4, $5)
+                         ON CONFLICT (did, rkey) DO UPDATE
+                         SET cid = EXCLUDED.cid,
+                             subject = EXCLUDED.subject,
+                             created_at = EXCLUDED.created_at",
+                        like.did,
+                        like.rkey,
+                        like.cid,
+                        like.subject,
+                        like.created_at,
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                PendingWrite::Follow(follow) => {
+                    sqlx::query!(
+                        "INSERT INTO follow (did, rkey, cid, subject, created_at)
+                         VALUES ($1, $2, $3, $4, $5)
+                         ON CONFLICT (did, rkey) DO UPDATE
+                         SET cid = EXCLUDED.cid,
+                             subject = EXCLUDED.subject,
+                             created_at = EXCLUDED.created_at",
+                        follow.did,
+                        follow.rkey,
+                        follow.cid,
+                        follow.subject,
+                        follow.created_at,
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                PendingWrite::Repost(repost) => {
+                    sqlx::query!(
+                        "INSERT INTO repost (did, rkey, cid, subject, created_at)
+                         VALUES ($1, $2, $3, $4, $5)
+                         ON CONFLICT (did, rkey) DO UPDATE
+                         SET cid = EXCLUDED.cid,
+                             subject = EXCLUDED.subject,
+                             created_at = EXCLUDED.created_at",
+                        repost.did,
+                        repost.rkey,
+                        repost.cid,
+                        repost.subject,
+                        repost.created_at,
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                PendingWrite::Profile(profile) => {
+                    sqlx::query!(
+                        "INSERT INTO profile (did, rkey, cid, display_name, description)
+                         VALUES ($1, $2, $3, $4, $5)
+                         ON CONFLICT (did, rkey) DO UPDATE
+                         SET cid = EXCLUDED.cid,
+                             display_name = EXCLUDED.display_name,
+                             description = EXCLUDED.description",
+                        profile.did,
+                        profile.rkey,
+                        profile.cid,
+                        profile.display_name,
+                        profile.description,
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                PendingWrite::Delete(tombstone) => {
+                    // `schema.surql`'s collections map 1:1 onto tables here,
+                    // so the NSID on the tombstone picks the table the same
+                    // way `FirehoseConsumer::decode_op` dispatches on it.
+                    match tombstone.collection.as_str() {
+                        c if c == atrium_api::app::bsky::feed::Post::NSID => {
+                            sqlx::query!(
+                                "DELETE FROM post WHERE did = $1 AND rkey = $2",
+                                tombstone.did,
+                                tombstone.rkey,
+                            )
+                            .execute(&mut *tx)
+                            .await?;
+                        }
+                        c if c == atrium_api::app::bsky::feed::Like::NSID => {
+                            sqlx::query!(
+                                "DELETE FROM like_ WHERE did = $1 AND rkey = $2",
+                                tombstone.did,
+                                tombstone.rkey,
+                            )
+                            .execute(&mut *tx)
+                            .await?;
+                        }
+                        c if c == atrium_api::app::bsky::graph::Follow::NSID => {
+                            sqlx::query!(
+                                "DELETE FROM follow WHERE did = $1 AND rkey = $2",
+                                tombstone.did,
+                                tombstone.rkey,
+                            )
+                            .execute(&mut *tx)
+                            .await?;
+                        }
+                        c if c == atrium_api::app::bsky::feed::Repost::NSID => {
+                            sqlx::query!(
+                                "DELETE FROM repost WHERE did = $1 AND rkey = $2",
+                                tombstone.did,
+                                tombstone.rkey,
+                            )
+                            .execute(&mut *tx)
+                            .await?;
+                        }
+                        c if c == atrium_api::app::bsky::actor::Profile::NSID => {
+                            sqlx::query!(
+                                "DELETE FROM profile WHERE did = $1 AND rkey = $2",
+                                tombstone.did,
+                                tombstone.rkey,
+                            )
+                            .execute(&mut *tx)
+                            .await?;
+                        }
+                        other => {
+                            tracing::trace!(collection = other, "no Postgres table for collection, skipping delete");
+                        }
+                    }
+                }
+            }
+        }
+
+        tx.commit().await?;
+        self.last_flush = std::time::Instant::now();
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl RecordStore for PostgresExporter {
+    async fn put_post(&mut self, post: Post) -> Result<()> {
+        self.enqueue(PendingWrite::Post(post)).await
+    }
+
+    async fn put_like(&mut self, like: Like) -> Result<()> {
+        self.enqueue(PendingWrite::Like(like)).await
+    }
+
+    async fn put_follow(&mut self, follow: Follow) -> Result<()> {
+        self.enqueue(PendingWrite::Follow(follow)).await
+    }
+
+    async fn put_repost(&mut self, repost: Repost) -> Result<()> {
+        self.enqueue(PendingWrite::Repost(repost)).await
+    }
+
+    async fn put_profile(&mut self, profile: Profile) -> Result<()> {
+        self.enqueue(PendingWrite::Profile(profile)).await
+    }
+
+    async fn delete(&mut self, tombstone: Tombstone) -> Result<()> {
+        self.enqueue(PendingWrite::Delete(tombstone)).await
+    }
+
+    /// Drains whatever's left in `self.pending` so a graceful shutdown
+    /// doesn't strand a partial batch that hasn't hit `batch_size` or
+    /// `flush_interval` yet.
+    async fn shutdown(&mut self) -> Result<()> {
+        self.flush().await
+    }
+}
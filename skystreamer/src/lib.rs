@@ -0,0 +1,309 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use atrium_api::{
+    app::bsky::{
+        actor::Profile as BProfile,
+        feed::{Like as BLike, Post as BPost, Repost as BRepost},
+        graph::Follow as BFollow,
+    },
+    com::atproto::sync::subscribe_repos::{Commit, RepoOp, NSID},
+    types::{CidLink, Collection},
+};
+use color_eyre::{eyre::eyre, Result};
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use types::{CidOld, CommitHandler, InfoPayload, PostData, Subscription};
+use update_rate::RateCounter;
+
+use crate::metrics::Metrics;
+use crate::types::Frame;
+
+pub mod config;
+pub mod db_types;
+pub mod exporter;
+pub mod metrics;
+pub mod types;
+
+/// Smallest and largest delay between reconnect attempts.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+pub struct RepoSubscription {
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl RepoSubscription {
+    /// Connects to `bgs`, resuming from `cursor` (the last persisted `seq`)
+    /// if one is given, or from "now" otherwise.
+    pub async fn new(bgs: &str, cursor: Option<u64>) -> Result<Self, Box<dyn std::error::Error>> {
+        let url = match cursor {
+            Some(seq) => format!("wss://{bgs}/xrpc/{NSID}?cursor={seq}"),
+            None => format!("wss://{bgs}/xrpc/{NSID}"),
+        };
+        let (stream, _) = connect_async(url).await?;
+        Ok(RepoSubscription { stream })
+    }
+
+    /// Runs the consumer against `bgs` until the process is killed,
+    /// reconnecting with exponential backoff whenever the websocket closes
+    /// or errors, and resuming from the last cursor persisted via
+    /// `handler.update_cursor`.
+    pub async fn run_resumable(
+        bgs: &str,
+        mut handler: impl CommitHandler,
+        mut cursor: Option<u64>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut backoff = INITIAL_BACKOFF;
+
+        loop {
+            let mut sub = match Self::new(bgs, cursor).await {
+                Ok(sub) => sub,
+                Err(err) => {
+                    tracing::warn!("failed to connect to {bgs}: {err}; retrying in {backoff:?}");
+                    handler.record_reconnect().await?;
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+            };
+            backoff = INITIAL_BACKOFF;
+
+            let mut commit_count = 0;
+            while let Some(result) = sub.next().await {
+                match result {
+                    Ok(Frame::Message(Some(t), message)) if t == "#commit" => {
+                        let commit: Commit =
+                            serde_ipld_dagcbor::from_reader(message.body.as_slice())?;
+                        if let Err(err) = handler.handle_commit(&commit).await {
+                            tracing::error!("FAILED: {err:?}");
+                        }
+                        commit_count += 1;
+                        if commit_count >= 20 {
+                            handler.update_cursor(commit.seq as u64).await?;
+                            cursor = Some(commit.seq as u64);
+                            commit_count = 0;
+                        }
+                    }
+                    Ok(Frame::Message(Some(t), message)) if t == "#info" => {
+                        if let Ok(info) =
+                            serde_ipld_dagcbor::from_reader::<InfoPayload, _>(message.body.as_slice())
+                        {
+                            if info.name == "OutdatedCursor" {
+                                tracing::warn!(
+                                    "server reports our cursor is outdated, resuming from its suggested position"
+                                );
+                                cursor = None;
+                            } else {
+                                tracing::debug!(name = %info.name, "received #info frame");
+                            }
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(err) => tracing::warn!("failed to decode frame: {err}"),
+                }
+            }
+
+            tracing::warn!("firehose connection to {bgs} closed, reconnecting in {backoff:?}");
+            handler.record_reconnect().await?;
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+}
+
+impl Subscription for RepoSubscription {
+    async fn next(&mut self) -> Option<Result<Frame, <Frame as TryFrom<&[u8]>>::Error>> {
+        if let Some(Ok(Message::Binary(data))) = self.stream.next().await {
+            Some(Frame::try_from(data.as_slice()))
+        } else {
+            None
+        }
+    }
+}
+
+pub struct FirehoseConsumer {
+    rate_counter: update_rate::DiscreteRateCounter,
+    /// Shared so a shutdown signal handler can flush the exporter
+    /// independently of `RepoSubscription::run_resumable`, which otherwise
+    /// holds the only handle to it for as long as the firehose loop runs.
+    exporter: Arc<tokio::sync::Mutex<Box<dyn exporter::Exporter>>>,
+    metrics: Arc<Metrics>,
+    /// Sidecar file the last persisted `seq` is written to, so a restart
+    /// can resume instead of starting over from "now".
+    cursor_path: std::path::PathBuf,
+}
+
+impl FirehoseConsumer {
+    pub fn new(
+        exporter: Box<dyn exporter::Exporter>,
+        metrics: Arc<Metrics>,
+        cursor_path: impl Into<std::path::PathBuf>,
+    ) -> Self {
+        FirehoseConsumer {
+            rate_counter: update_rate::DiscreteRateCounter::new(50),
+            exporter: Arc::new(tokio::sync::Mutex::new(exporter)),
+            metrics,
+            cursor_path: cursor_path.into(),
+        }
+    }
+
+    /// Reads the last persisted cursor back, if any.
+    pub async fn load_cursor(&self) -> Option<u64> {
+        tokio::fs::read_to_string(&self.cursor_path)
+            .await
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+    }
+
+    /// A handle to the exporter that outlives `self`, so it can be flushed
+    /// from a shutdown signal handler running alongside
+    /// `RepoSubscription::run_resumable(bgs, self, ...)`, which
+    /// otherwise takes ownership of the consumer for as long as the
+    /// firehose loop runs.
+    pub fn exporter_handle(&self) -> Arc<tokio::sync::Mutex<Box<dyn exporter::Exporter>>> {
+        self.exporter.clone()
+    }
+}
+
+impl CommitHandler for FirehoseConsumer {
+    async fn update_cursor(&self, seq: u64) -> Result<()> {
+        tracing::trace!("Persisting cursor at seq {}", seq);
+        self.metrics.last_cursor.store(seq, Ordering::Relaxed);
+        tokio::fs::write(&self.cursor_path, seq.to_string()).await?;
+        Ok(())
+    }
+
+    async fn record_reconnect(&self) -> Result<()> {
+        self.metrics.reconnects.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, commit))]
+    async fn handle_commit(&mut self, commit: &Commit) -> Result<()> {
+        self.metrics.commits_processed.fetch_add(1, Ordering::Relaxed);
+        for op in &commit.ops {
+            let Some(record) = self.decode_op(commit, op).await? else {
+                continue;
+            };
+
+            tracing::trace!(?record, "Received record");
+
+            if let Err(err) = self.exporter.lock().await.export(record).await {
+                self.metrics.export_errors.fetch_add(1, Ordering::Relaxed);
+                return Err(err);
+            }
+            self.metrics.records_exported.fetch_add(1, Ordering::Relaxed);
+
+            self.update_stats();
+        }
+        Ok(())
+    }
+}
+
+impl FirehoseConsumer {
+    /// Collection NSID a `RepoOp`'s path starts with, e.g.
+    /// `app.bsky.feed.post` out of `app.bsky.feed.post/3juj...`.
+    fn collection(op: &RepoOp) -> &str {
+        op.path.split('/').next().unwrap_or_default()
+    }
+
+    /// Decodes a single `RepoOp` into the typed [`db_types::Record`] it
+    /// represents, dispatching on collection NSID and action. Deletes carry
+    /// a path but no block, so they become a tombstone instead of a decode
+    /// attempt. Returns `None` for collections/actions we don't mirror yet.
+    async fn decode_op(&self, commit: &Commit, op: &RepoOp) -> Result<Option<db_types::Record>> {
+        let did = commit.repo.to_string();
+        let cid = commit.commit.to_string();
+        let collection = Self::collection(op);
+        let rkey = op.path.split('/').nth(1).unwrap_or_default().to_string();
+
+        if op.action.as_str() == "delete" {
+            return Ok(Some(db_types::Record::Deleted(db_types::Tombstone {
+                did,
+                collection: collection.to_string(),
+                rkey,
+            })));
+        }
+
+        if !matches!(op.action.as_str(), "create" | "update") {
+            return Ok(None);
+        }
+
+        let record = match collection {
+            c if c == BPost::NSID => {
+                let raw: atrium_api::app::bsky::feed::post::Record =
+                    self.extract_record(op, &commit.blocks).await?;
+                let post = PostData::new(commit.repo.clone(), commit.commit.clone(), raw);
+                db_types::Record::Post(db_types::Post::new(post, rkey))
+            }
+            c if c == BLike::NSID => {
+                let raw: atrium_api::app::bsky::feed::like::Record =
+                    self.extract_record(op, &commit.blocks).await?;
+                db_types::Record::Like(db_types::Like::new(did, rkey, cid, raw))
+            }
+            c if c == BFollow::NSID => {
+                let raw: atrium_api::app::bsky::graph::follow::Record =
+                    self.extract_record(op, &commit.blocks).await?;
+                db_types::Record::Follow(db_types::Follow::new(did, rkey, cid, raw))
+            }
+            c if c == BRepost::NSID => {
+                let raw: atrium_api::app::bsky::feed::repost::Record =
+                    self.extract_record(op, &commit.blocks).await?;
+                db_types::Record::Repost(db_types::Repost::new(did, rkey, cid, raw))
+            }
+            c if c == BProfile::NSID => {
+                let raw: atrium_api::app::bsky::actor::profile::Record =
+                    self.extract_record(op, &commit.blocks).await?;
+                db_types::Record::Profile(db_types::Profile::new(did, rkey, cid, raw))
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(record))
+    }
+
+    async fn extract_record<T: DeserializeOwned>(
+        &self,
+        op: &RepoOp,
+        mut blocks: &[u8],
+    ) -> Result<T> {
+        let (items, _) = rs_car::car_read_all(&mut blocks, true).await?;
+
+        let (_, item) = items
+            .iter()
+            .find(|(cid, _)| {
+                let converted_cid = match CidOld::from(*cid).try_into() {
+                    Ok(cid) => CidLink(cid),
+                    Err(err) => {
+                        tracing::warn!("skipping CAR block with unconvertible CID: {err}");
+                        return false;
+                    }
+                };
+                Some(converted_cid) == op.cid
+            })
+            .ok_or_else(|| {
+                eyre!(
+                    "Could not find item with operation cid {:?} out of {} items",
+                    op.cid,
+                    items.len()
+                )
+            })?;
+
+        Ok(serde_ipld_dagcbor::from_reader(&mut item.as_slice())?)
+    }
+
+    fn update_stats(&mut self) {
+        self.rate_counter.update();
+        self.metrics.set_rate(self.rate_counter.rate());
+        if self.rate_counter.rate_age_cycles() == 0 {
+            tracing::info!(
+                "Ingest rate: {rate:.2} items/s",
+                rate = self.rate_counter.rate()
+            );
+        }
+    }
+}
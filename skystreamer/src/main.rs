@@ -0,0 +1,35 @@
+use clap::Parser;
+use skystreamer::{config::Config, RepoSubscription};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_target(false)
+        .with_thread_ids(false)
+        .with_level(true)
+        .with_file(false)
+        .compact()
+        .with_line_number(false)
+        .with_env_filter("info")
+        .init();
+
+    let config = Config::parse();
+    let consumer = config.subscribe().await?;
+    let exporter = consumer.exporter_handle();
+    let cursor = consumer.load_cursor().await;
+    if let Some(seq) = cursor {
+        tracing::info!("Resuming firehose from persisted cursor at seq {seq}");
+    }
+
+    tokio::select! {
+        result = RepoSubscription::run_resumable(&config.bgs, consumer, cursor) => {
+            result?;
+        }
+        _ = tokio::signal::ctrl_c() => {
+            tracing::info!("received shutdown signal, flushing exporter");
+            exporter.lock().await.shutdown().await?;
+        }
+    }
+
+    Ok(())
+}
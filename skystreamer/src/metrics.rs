@@ -0,0 +1,81 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use axum::{routing::get, Router};
+use color_eyre::Result;
+
+/// Counters and gauges for the ingest pipeline, exported in Prometheus text
+/// format off an embedded HTTP server so a continuously-running streamer is
+/// observable from Grafana/Prometheus rather than only grep-able logs.
+#[derive(Default)]
+pub struct Metrics {
+    pub commits_processed: AtomicU64,
+    pub records_exported: AtomicU64,
+    pub export_errors: AtomicU64,
+    pub reconnects: AtomicU64,
+    pub last_cursor: AtomicU64,
+    pub items_per_sec: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Renders the current counters as Prometheus exposition-format text.
+    fn render(&self) -> String {
+        format!(
+            "# HELP skystreamer_commits_processed_total Commits processed off the firehose\n\
+             # TYPE skystreamer_commits_processed_total counter\n\
+             skystreamer_commits_processed_total {commits}\n\
+             # HELP skystreamer_records_exported_total Records successfully handed to the exporter\n\
+             # TYPE skystreamer_records_exported_total counter\n\
+             skystreamer_records_exported_total {posts}\n\
+             # HELP skystreamer_export_errors_total Errors returned by the exporter\n\
+             # TYPE skystreamer_export_errors_total counter\n\
+             skystreamer_export_errors_total {errors}\n\
+             # HELP skystreamer_reconnects_total Firehose websocket reconnects\n\
+             # TYPE skystreamer_reconnects_total counter\n\
+             skystreamer_reconnects_total {reconnects}\n\
+             # HELP skystreamer_last_cursor_seq Last persisted firehose cursor seq\n\
+             # TYPE skystreamer_last_cursor_seq gauge\n\
+             skystreamer_last_cursor_seq {cursor}\n\
+             # HELP skystreamer_items_per_sec Current ingest rate, items/sec\n\
+             # TYPE skystreamer_items_per_sec gauge\n\
+             skystreamer_items_per_sec {rate}\n",
+            commits = self.commits_processed.load(Ordering::Relaxed),
+            posts = self.records_exported.load(Ordering::Relaxed),
+            errors = self.export_errors.load(Ordering::Relaxed),
+            reconnects = self.reconnects.load(Ordering::Relaxed),
+            cursor = self.last_cursor.load(Ordering::Relaxed),
+            rate = self.items_per_sec.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Stores the current ingest rate as an integer items/sec gauge.
+    pub fn set_rate(&self, rate: f64) {
+        self.items_per_sec.store(rate.round() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Serves `/metrics` (Prometheus text) and `/healthz` (liveness) on
+/// `addr` until the process exits.
+pub async fn serve(addr: std::net::SocketAddr, metrics: Arc<Metrics>) -> Result<()> {
+    let app = Router::new()
+        .route(
+            "/metrics",
+            get({
+                let metrics = metrics.clone();
+                move || {
+                    let metrics = metrics.clone();
+                    async move { metrics.render() }
+                }
+            }),
+        )
+        .route("/healthz", get(|| async { "ok" }));
+
+    tracing::info!("Serving metrics on http://{addr}/metrics");
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await?;
+    Ok(())
+}
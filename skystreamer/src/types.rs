@@ -0,0 +1,119 @@
+use atrium_api::app::bsky::feed::post::Record as PostRecord;
+use atrium_api::com::atproto::sync::subscribe_repos::Commit;
+use color_eyre::{eyre::eyre, Result};
+use serde::Deserialize;
+
+/// Header that precedes every frame body on the `subscribeRepos` websocket.
+#[derive(Debug, Clone, Deserialize)]
+struct FrameHeader {
+    op: i8,
+    t: Option<String>,
+}
+
+/// Body of a `#commit` or `#info` frame, still CBOR-encoded.
+#[derive(Debug, Clone)]
+pub struct MessageFrame {
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorFrame {
+    pub error: String,
+    pub message: Option<String>,
+}
+
+/// A single frame decoded off the firehose websocket.
+#[derive(Debug, Clone)]
+pub enum Frame {
+    Message(Option<String>, MessageFrame),
+    Error(ErrorFrame),
+}
+
+impl TryFrom<&[u8]> for Frame {
+    type Error = color_eyre::eyre::Error;
+
+    fn try_from(data: &[u8]) -> std::result::Result<Self, Self::Error> {
+        let mut cursor = data;
+        let header: FrameHeader = serde_ipld_dagcbor::from_reader(&mut cursor)?;
+        match header.op {
+            1 => Ok(Frame::Message(
+                header.t,
+                MessageFrame {
+                    body: cursor.to_vec(),
+                },
+            )),
+            -1 => Ok(Frame::Error(serde_ipld_dagcbor::from_reader(&mut cursor)?)),
+            op => Err(eyre!("unrecognized frame op {op}")),
+        }
+    }
+}
+
+/// A source of decoded firehose frames, e.g. a websocket connection.
+pub trait Subscription {
+    async fn next(&mut self) -> Option<Result<Frame, <Frame as TryFrom<&[u8]>>::Error>>;
+}
+
+/// Handles decoded commits off the firehose.
+pub trait CommitHandler {
+    async fn update_cursor(&self, seq: u64) -> Result<()>;
+    async fn handle_commit(&mut self, commit: &Commit) -> Result<()>;
+
+    /// Called whenever the firehose connection drops and is about to be
+    /// retried. Default no-op; implementations that track metrics can
+    /// override it.
+    async fn record_reconnect(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Body of an `#info` frame, e.g. the server telling us our cursor is too
+/// far behind to resume from.
+#[derive(Debug, Clone, Deserialize)]
+pub struct InfoPayload {
+    pub name: String,
+    pub message: Option<String>,
+}
+
+/// An old-style `cid::Cid` as produced by `rs_car`, kept distinct from
+/// `atrium_api`'s own CID type so the conversion at the CAR/CBOR boundary
+/// stays explicit.
+#[derive(Debug, Clone, Copy)]
+pub struct CidOld(pub cid::Cid);
+
+impl From<cid::Cid> for CidOld {
+    fn from(cid: cid::Cid) -> Self {
+        CidOld(cid)
+    }
+}
+
+impl TryFrom<CidOld> for atrium_api::types::string::Cid {
+    type Error = color_eyre::eyre::Error;
+
+    fn try_from(old: CidOld) -> std::result::Result<Self, Self::Error> {
+        atrium_api::types::string::Cid::new(old.0.to_string().parse()?)
+            .map_err(|e| eyre!("invalid CID conversion: {e}"))
+    }
+}
+
+/// Raw ingredients for a post record pulled off a `#commit` frame, before
+/// it's shaped into the exporter-facing [`crate::db_types::Post`].
+#[derive(Debug, Clone)]
+pub struct PostData {
+    pub did: String,
+    pub cid: String,
+    pub record: PostRecord,
+}
+
+impl PostData {
+    pub fn new(
+        did: atrium_api::types::string::Did,
+        cid: atrium_api::types::string::Cid,
+        record: PostRecord,
+    ) -> Self {
+        PostData {
+            did: did.to_string(),
+            cid: cid.to_string(),
+            record,
+        }
+    }
+}